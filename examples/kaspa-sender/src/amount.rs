@@ -0,0 +1,175 @@
+//! Human-readable KAS amount parsing, shared between the CLI and any future library callers.
+//!
+//! `--amount` accepts either raw sompi (`4000000000`, with `--unit sompi`) or a decimal KAS
+//! string (`40`, `40.0`, `40.0 KAS`, with the default `--unit kas`), converted using the fixed
+//! 1 KAS = 100,000,000 sompi factor. The unit is never guessed from the input.
+
+use std::fmt;
+
+/// Number of sompi in one KAS.
+pub const SOMPI_PER_KAS: u64 = 100_000_000;
+
+/// KAS amounts are rejected past this many digits after the decimal point, rather than
+/// silently truncated to the nearest sompi.
+const MAX_FRACTIONAL_DIGITS: usize = 8;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, clap::ValueEnum)]
+pub enum Unit {
+    /// Raw sompi, the smallest Kaspa unit
+    Sompi,
+    /// Whole or fractional KAS (1 KAS = 100,000,000 sompi)
+    Kas,
+}
+
+/// Error returned by [`parse_amount`] for malformed or out-of-range input.
+#[derive(Debug, PartialEq, Eq)]
+pub enum AmountParseError {
+    /// The input wasn't a valid integer (for `--unit sompi`) or decimal (for `--unit kas`)
+    NotANumber(String),
+    /// More than [`MAX_FRACTIONAL_DIGITS`] digits were given after the decimal point
+    TooManyFractionalDigits(String),
+    /// The amount doesn't fit in a `u64` sompi value
+    Overflow(String),
+}
+
+impl fmt::Display for AmountParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AmountParseError::NotANumber(s) => write!(f, "'{}' is not a valid amount", s),
+            AmountParseError::TooManyFractionalDigits(s) => write!(
+                f,
+                "'{}' has more than {} fractional digits",
+                s, MAX_FRACTIONAL_DIGITS
+            ),
+            AmountParseError::Overflow(s) => write!(f, "'{}' overflows a sompi amount", s),
+        }
+    }
+}
+
+impl std::error::Error for AmountParseError {}
+
+/// Parses `input` as an amount in `unit`, returning the equivalent sompi value.
+///
+/// For [`Unit::Kas`], `input` may carry a trailing (case-insensitive) `KAS` suffix and up to
+/// [`MAX_FRACTIONAL_DIGITS`] fractional digits.
+pub fn parse_amount(input: &str, unit: Unit) -> Result<u64, AmountParseError> {
+    let trimmed = input.trim();
+    match unit {
+        Unit::Sompi => trimmed.parse::<u64>().map_err(|e| match e.kind() {
+            std::num::IntErrorKind::PosOverflow => AmountParseError::Overflow(input.to_string()),
+            _ => AmountParseError::NotANumber(input.to_string()),
+        }),
+        Unit::Kas => parse_kas(strip_kas_suffix(trimmed), input),
+    }
+}
+
+fn strip_kas_suffix(s: &str) -> &str {
+    let split = s.len().saturating_sub(3);
+    match s.get(split..) {
+        Some(suffix) if suffix.eq_ignore_ascii_case("kas") => s[..split].trim(),
+        _ => s,
+    }
+}
+
+fn parse_kas(s: &str, original: &str) -> Result<u64, AmountParseError> {
+    let (int_part, frac_part) = match s.split_once('.') {
+        Some((i, f)) => (i, f),
+        None => (s, ""),
+    };
+
+    if int_part.is_empty() && frac_part.is_empty() {
+        return Err(AmountParseError::NotANumber(original.to_string()));
+    }
+    if frac_part.len() > MAX_FRACTIONAL_DIGITS {
+        return Err(AmountParseError::TooManyFractionalDigits(
+            original.to_string(),
+        ));
+    }
+    if !int_part.chars().all(|c| c.is_ascii_digit())
+        || !frac_part.chars().all(|c| c.is_ascii_digit())
+    {
+        return Err(AmountParseError::NotANumber(original.to_string()));
+    }
+
+    let whole: u64 = if int_part.is_empty() {
+        0
+    } else {
+        int_part
+            .parse()
+            .map_err(|_| AmountParseError::Overflow(original.to_string()))?
+    };
+    let whole_sompi = whole
+        .checked_mul(SOMPI_PER_KAS)
+        .ok_or_else(|| AmountParseError::Overflow(original.to_string()))?;
+
+    let padded_frac = format!("{:0<width$}", frac_part, width = MAX_FRACTIONAL_DIGITS);
+    let frac_sompi: u64 = padded_frac
+        .parse()
+        .map_err(|_| AmountParseError::Overflow(original.to_string()))?;
+
+    whole_sompi
+        .checked_add(frac_sompi)
+        .ok_or_else(|| AmountParseError::Overflow(original.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_raw_sompi() {
+        assert_eq!(parse_amount("4000000000", Unit::Sompi), Ok(4_000_000_000));
+    }
+
+    #[test]
+    fn rejects_non_numeric_sompi() {
+        assert!(parse_amount("40.5", Unit::Sompi).is_err());
+    }
+
+    #[test]
+    fn parses_whole_kas() {
+        assert_eq!(parse_amount("40", Unit::Kas), Ok(40 * SOMPI_PER_KAS));
+    }
+
+    #[test]
+    fn parses_decimal_kas() {
+        assert_eq!(parse_amount("40.5", Unit::Kas), Ok(4_050_000_000));
+    }
+
+    #[test]
+    fn parses_kas_with_suffix() {
+        assert_eq!(parse_amount("40.0 KAS", Unit::Kas), Ok(4_000_000_000));
+        assert_eq!(parse_amount("40 kas", Unit::Kas), Ok(4_000_000_000));
+    }
+
+    #[test]
+    fn parses_fractional_only_kas() {
+        assert_eq!(parse_amount(".5", Unit::Kas), Ok(50_000_000));
+    }
+
+    #[test]
+    fn rejects_too_many_fractional_digits() {
+        assert_eq!(
+            parse_amount("1.123456789", Unit::Kas),
+            Err(AmountParseError::TooManyFractionalDigits(
+                "1.123456789".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        assert!(parse_amount("forty", Unit::Kas).is_err());
+        assert!(parse_amount("", Unit::Kas).is_err());
+    }
+
+    #[test]
+    fn rejects_overflow() {
+        assert_eq!(
+            parse_amount("999999999999999999999", Unit::Kas),
+            Err(AmountParseError::Overflow(
+                "999999999999999999999".to_string()
+            ))
+        );
+    }
+}
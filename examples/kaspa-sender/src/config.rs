@@ -0,0 +1,127 @@
+//! Persisted CLI configuration (default: ~/.config/dymension-kaspa/config.toml).
+//!
+//! Holds defaults for `--rpc`, `--escrow`, `--network`, and `--wallet-dir`; CLI flags always
+//! override whatever the file holds. Use `--config <path>` for separate profiles (e.g.
+//! mainnet vs testnet), and `init` to (re)create the file interactively.
+
+use kaspa_addresses::Address;
+use serde::{Deserialize, Serialize};
+use std::io::Write as _;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Config {
+    pub network: Option<String>,
+    pub rpc: Option<String>,
+    pub escrow: Option<String>,
+    pub wallet_dir: Option<String>,
+}
+
+impl Config {
+    /// The default config path: `~/.config/dymension-kaspa/config.toml`.
+    pub fn default_path() -> eyre::Result<PathBuf> {
+        let base = dirs::config_dir()
+            .ok_or_else(|| eyre::eyre!("could not determine the user config directory"))?;
+        Ok(base.join("dymension-kaspa").join("config.toml"))
+    }
+}
+
+/// Reads and parses the config file at `path`, returning `None` if it doesn't exist.
+pub fn read_config(path: &Path) -> eyre::Result<Option<Config>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let raw = std::fs::read_to_string(path)
+        .map_err(|e| eyre::eyre!("failed to read config file {}: {}", path.display(), e))?;
+    let config = toml::from_str(&raw)
+        .map_err(|e| eyre::eyre!("failed to parse config file {}: {}", path.display(), e))?;
+
+    Ok(Some(config))
+}
+
+/// Writes `config` to `path`, creating its parent directory if needed.
+pub fn write_config(path: &Path, config: &Config) -> eyre::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| {
+            eyre::eyre!(
+                "failed to create config directory {}: {}",
+                parent.display(),
+                e
+            )
+        })?;
+    }
+
+    let raw = toml::to_string_pretty(config)
+        .map_err(|e| eyre::eyre!("failed to serialize config: {}", e))?;
+    std::fs::write(path, raw)
+        .map_err(|e| eyre::eyre!("failed to write config file {}: {}", path.display(), e))?;
+
+    Ok(())
+}
+
+/// Loads the config at `path`, running the interactive first-run setup and persisting the
+/// result if it doesn't exist yet.
+pub fn initial_setup(path: &Path) -> eyre::Result<Config> {
+    match read_config(path)? {
+        Some(config) => Ok(config),
+        None => query_user_for_initial_config(path),
+    }
+}
+
+/// Interactively prompts for each config value, validates it, and writes the file.
+pub fn query_user_for_initial_config(path: &Path) -> eyre::Result<Config> {
+    eprintln!("setting up config at {}", path.display());
+
+    let network = prompt("Network (mainnet/testnet)", Some("mainnet"))?;
+    if network != "mainnet" && network != "testnet" {
+        return Err(eyre::eyre!("unknown network: {}", network));
+    }
+
+    let rpc = prompt("Kaspa WRPC URL (e.g. wss://your-node:17110)", None)?;
+
+    let escrow = prompt("Escrow address", None)?;
+    Address::try_from(escrow.as_str()).map_err(|e| eyre::eyre!("invalid escrow address: {}", e))?;
+
+    let wallet_dir = prompt("Wallet directory (blank for default ~/.kaspa/)", Some(""))?;
+
+    let config = Config {
+        network: Some(network),
+        rpc: Some(rpc),
+        escrow: Some(escrow),
+        wallet_dir: if wallet_dir.is_empty() {
+            None
+        } else {
+            Some(wallet_dir)
+        },
+    };
+
+    write_config(path, &config)?;
+    eprintln!("wrote config to {}", path.display());
+
+    Ok(config)
+}
+
+fn prompt(label: &str, default: Option<&str>) -> eyre::Result<String> {
+    match default {
+        Some(default) => eprint!("{} [{}]: ", label, default),
+        None => eprint!("{}: ", label),
+    }
+    std::io::stderr()
+        .flush()
+        .map_err(|e| eyre::eyre!("failed to flush prompt: {}", e))?;
+
+    let mut input = String::new();
+    std::io::stdin()
+        .read_line(&mut input)
+        .map_err(|e| eyre::eyre!("failed to read input: {}", e))?;
+    let input = input.trim().to_string();
+
+    if input.is_empty() {
+        return match default {
+            Some(default) => Ok(default.to_string()),
+            None => Err(eyre::eyre!("{} is required", label)),
+        };
+    }
+    Ok(input)
+}
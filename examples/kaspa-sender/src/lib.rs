@@ -0,0 +1,622 @@
+//! Library API for sending Kaspa deposit transactions carrying a Hyperlane payload.
+//!
+//! [`KaspaDepositSender`] wraps wallet connection, account selection, and transaction
+//! submission behind a small async API. Errors are returned as structured [`DepositError`]s,
+//! not `eyre::eyre!` strings, so embedders can pattern-match on failures (e.g. retry
+//! [`DepositError::Connect`], treat [`DepositError::InvalidAddress`] as permanent).
+//!
+//! `kaspa-sender`, the binary crate in this same package, is a thin CLI wrapper over this API.
+
+pub mod amount;
+
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use kaspa_addresses::Address;
+use kaspa_consensus_core::network::NetworkId;
+use kaspa_ledger::api::Ledger;
+use kaspa_ledger::transport::TransportHID;
+use kaspa_wallet_core::account::Account;
+use kaspa_wallet_core::events::Events;
+use kaspa_wallet_core::prelude::*;
+use kaspa_wallet_core::storage::local::set_default_storage_folder as unsafe_set_default_storage_folder_kaspa;
+use kaspa_wallet_core::tx::{Fees, Generator, GeneratorSettings, PendingTransaction, TransactionSignature};
+use kaspa_wallet_core::wallet::Wallet;
+use kaspa_wallet_keys::secret::Secret;
+use kaspa_wrpc_client::Resolver;
+use trezor_client::Trezor;
+use workflow_core::abortable::Abortable;
+
+/// Default BIP32 path for a Kaspa receive account (Ledger and Trezor agree on this one).
+pub const DEFAULT_HD_PATH: &str = "m/44'/111111'/0'/0/0";
+/// Legacy path used by early Ledger Kaspa app releases, kept for users with older setups.
+pub const LEGACY_LEDGER_HD_PATH: &str = "m/44'/972/0'/0/0";
+
+/// Where the signing key for deposits comes from.
+pub enum WalletSource {
+    /// A local password-protected rusty-kaspa keystore (the historical default)
+    Local {
+        secret: Secret,
+        /// Custom wallet directory, or `None` for the rusty-kaspa default (~/.kaspa/)
+        wallet_dir: Option<String>,
+    },
+    /// A Ledger hardware wallet, signing over HID with on-device confirmation
+    Ledger { hd_path: String },
+    /// A Trezor hardware wallet, signing over HID with on-device confirmation
+    Trezor { hd_path: String },
+}
+
+/// Configuration used to connect a [`KaspaDepositSender`].
+pub struct DepositSenderConfig {
+    pub network_id: NetworkId,
+    pub rpc_url: String,
+    pub wallet_source: WalletSource,
+}
+
+/// A structured error from connecting a wallet or sending a deposit, carrying the
+/// underlying source error so callers can distinguish retryable failures (e.g.
+/// [`DepositError::Connect`]) from permanent ones (e.g. [`DepositError::InvalidAddress`]).
+#[derive(Debug)]
+pub enum DepositError {
+    /// Failed to connect to the Kaspa RPC endpoint
+    Connect(eyre::Error),
+    /// Failed to open the local keystore, or to reach/unlock a hardware device
+    WalletOpen(eyre::Error),
+    /// The destination address failed to parse
+    InvalidAddress(eyre::Error),
+    /// The active account doesn't have enough mature balance to cover amount + fee
+    InsufficientFunds { required: u64, available: u64 },
+    /// Estimating, building, confirming on the device, signing, or broadcasting the
+    /// transaction failed
+    Broadcast(eyre::Error),
+    /// `wait_for_confirmations` gave up before reaching the requested confirmation count
+    Timeout {
+        tx_id: TransactionId,
+        confirmations: u64,
+        timeout: Duration,
+        /// Confirmations actually observed before giving up, so callers (e.g. a relayer)
+        /// can decide whether to keep polling instead of resubmitting.
+        last_confirmations: u64,
+    },
+}
+
+impl fmt::Display for DepositError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DepositError::Connect(e) => write!(f, "failed to connect to kaspa node: {e}"),
+            DepositError::WalletOpen(e) => write!(f, "failed to open wallet: {e}"),
+            DepositError::InvalidAddress(e) => write!(f, "invalid address: {e}"),
+            DepositError::InsufficientFunds {
+                required,
+                available,
+            } => write!(
+                f,
+                "insufficient funds: need {required} sompi, have {available} sompi"
+            ),
+            DepositError::Broadcast(e) => write!(f, "failed to broadcast transaction: {e}"),
+            DepositError::Timeout {
+                tx_id,
+                confirmations,
+                timeout,
+                last_confirmations,
+            } => write!(
+                f,
+                "timed out after {timeout:?} waiting for {confirmations} confirmations on {tx_id} \
+                 (last seen: {last_confirmations})"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DepositError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DepositError::Connect(e)
+            | DepositError::WalletOpen(e)
+            | DepositError::InvalidAddress(e)
+            | DepositError::Broadcast(e) => Some(e.as_ref()),
+            DepositError::InsufficientFunds { .. } | DepositError::Timeout { .. } => None,
+        }
+    }
+}
+
+/// A connected signing backend: either the local keystore secret, or a hardware device that
+/// signs over HID and shows the transaction on its screen.
+enum Signer {
+    Local(Secret),
+    Ledger { device: Ledger, hd_path: String },
+    Trezor { device: Trezor, hd_path: String },
+}
+
+impl Signer {
+    async fn connect(source: WalletSource) -> Result<Self, DepositError> {
+        match source {
+            WalletSource::Local { secret, .. } => Ok(Signer::Local(secret)),
+            WalletSource::Ledger { hd_path } => {
+                let transport = TransportHID::open()
+                    .await
+                    .map_err(|e| DepositError::WalletOpen(eyre::eyre!("ledger HID open: {}", e)))?;
+                let device = Ledger::connect(transport)
+                    .await
+                    .map_err(|e| DepositError::WalletOpen(eyre::eyre!("ledger connect: {}", e)))?;
+                Ok(Signer::Ledger { device, hd_path })
+            }
+            WalletSource::Trezor { hd_path } => {
+                let device = Trezor::connect_hid()
+                    .await
+                    .map_err(|e| DepositError::WalletOpen(eyre::eyre!("trezor connect: {}", e)))?;
+                Ok(Signer::Trezor { device, hd_path })
+            }
+        }
+    }
+
+    /// The Kaspa address owning the UTXOs to spend from, derived from the device (or, for
+    /// `Local`, left for the wallet's active account to supply).
+    async fn address(&self, network_id: NetworkId) -> Result<Option<Address>, DepositError> {
+        match self {
+            Signer::Local(_) => Ok(None),
+            Signer::Ledger { device, hd_path } => Ok(Some(
+                device
+                    .derive_address(hd_path, network_id.into())
+                    .await
+                    .map_err(|e| DepositError::WalletOpen(eyre::eyre!("ledger derive address: {}", e)))?,
+            )),
+            Signer::Trezor { device, hd_path } => Ok(Some(
+                device
+                    .derive_address(hd_path, network_id.into())
+                    .await
+                    .map_err(|e| DepositError::WalletOpen(eyre::eyre!("trezor derive address: {}", e)))?,
+            )),
+        }
+    }
+
+    /// Ask the device to render the escrow address and sompi amount and wait for the user
+    /// to confirm. No-op for the local signer, which has no screen.
+    async fn confirm_on_device(&self, escrow: &Address, amount: u64) -> Result<(), DepositError> {
+        match self {
+            Signer::Local(_) => Ok(()),
+            Signer::Ledger { device, .. } => {
+                eprintln!("confirm the escrow address and amount on your device...");
+                device
+                    .display_transaction(escrow, amount)
+                    .await
+                    .map_err(|e| DepositError::Broadcast(eyre::eyre!("ledger confirmation: {}", e)))
+            }
+            Signer::Trezor { device, .. } => {
+                eprintln!("confirm the escrow address and amount on your device...");
+                device
+                    .display_transaction(escrow, amount)
+                    .await
+                    .map_err(|e| DepositError::Broadcast(eyre::eyre!("trezor confirmation: {}", e)))
+            }
+        }
+    }
+
+    /// Sends `pending_tx` to the device over HID for signing and returns the signatures it
+    /// produces for each input. The wallet-file keystore never holds a hardware signer's
+    /// key, so signing happens entirely on the device, not via `Account::send`.
+    async fn sign_inputs(
+        &self,
+        pending_tx: &PendingTransaction,
+    ) -> Result<Vec<TransactionSignature>, DepositError> {
+        match self {
+            Signer::Local(_) => unreachable!("local signer signs via Account::send, not sign_inputs"),
+            Signer::Ledger { device, hd_path } => device
+                .sign_transaction(pending_tx, hd_path)
+                .await
+                .map_err(|e| DepositError::Broadcast(eyre::eyre!("ledger sign transaction: {}", e))),
+            Signer::Trezor { device, hd_path } => device
+                .sign_transaction(pending_tx, hd_path)
+                .await
+                .map_err(|e| DepositError::Broadcast(eyre::eyre!("trezor sign transaction: {}", e))),
+        }
+    }
+}
+
+/// A connected wallet and signer, ready to send deposit transactions.
+pub struct KaspaDepositSender {
+    wallet: Arc<Wallet>,
+    network_id: NetworkId,
+    signer: Signer,
+}
+
+impl KaspaDepositSender {
+    /// Connects to `config.rpc_url`, opens the wallet (local keystore or hardware device),
+    /// and activates the account that deposits will be sent from.
+    pub async fn connect(config: DepositSenderConfig) -> Result<Self, DepositError> {
+        let wallet_dir = match &config.wallet_source {
+            WalletSource::Local { wallet_dir, .. } => wallet_dir.clone(),
+            _ => None,
+        };
+        if let Some(wallet_dir) = wallet_dir {
+            unsafe { unsafe_set_default_storage_folder_kaspa(wallet_dir) }
+                .map_err(|e| DepositError::WalletOpen(eyre::eyre!("set storage folder: {}", e)))?;
+        }
+
+        let signer = Signer::connect(config.wallet_source).await?;
+
+        let local_store = Wallet::local_store()
+            .map_err(|e| DepositError::WalletOpen(eyre::eyre!("open local store: {}", e)))?;
+
+        let wallet = Arc::new(
+            Wallet::try_new(local_store, Some(Resolver::default()), Some(config.network_id))
+                .map_err(|e| DepositError::Connect(eyre::eyre!("create wallet: {}", e)))?,
+        );
+
+        wallet
+            .start()
+            .await
+            .map_err(|e| DepositError::Connect(eyre::eyre!("start wallet: {}", e)))?;
+
+        wallet
+            .clone()
+            .connect(Some(config.rpc_url), &config.network_id)
+            .await
+            .map_err(|e| DepositError::Connect(eyre::eyre!("connect wallet: {}", e)))?;
+
+        if !wallet.is_connected() {
+            return Err(DepositError::Connect(eyre::eyre!("wallet not connected")));
+        }
+
+        let account_id = match &signer {
+            Signer::Local(secret) => {
+                wallet
+                    .clone()
+                    .wallet_open(secret.clone(), None, true, false)
+                    .await
+                    .map_err(|e| DepositError::WalletOpen(eyre::eyre!("open wallet: {}", e)))?;
+
+                let accounts = wallet
+                    .clone()
+                    .accounts_enumerate()
+                    .await
+                    .map_err(|e| DepositError::WalletOpen(eyre::eyre!("enumerate accounts: {}", e)))?;
+
+                accounts
+                    .first()
+                    .ok_or_else(|| DepositError::WalletOpen(eyre::eyre!("wallet has no accounts")))?
+                    .account_id
+            }
+            Signer::Ledger { .. } | Signer::Trezor { .. } => {
+                let derived_address = signer.address(config.network_id).await?.ok_or_else(|| {
+                    DepositError::WalletOpen(eyre::eyre!("hardware signer did not return an address"))
+                })?;
+
+                wallet
+                    .clone()
+                    .accounts_create_watch_only(derived_address)
+                    .await
+                    .map_err(|e| DepositError::WalletOpen(eyre::eyre!("import hardware account: {}", e)))?
+            }
+        };
+
+        wallet
+            .clone()
+            .accounts_select(Some(account_id))
+            .await
+            .map_err(|e| DepositError::WalletOpen(eyre::eyre!("select account: {}", e)))?;
+
+        wallet
+            .clone()
+            .accounts_activate(Some(vec![account_id]))
+            .await
+            .map_err(|e| DepositError::WalletOpen(eyre::eyre!("activate account: {}", e)))?;
+
+        Ok(KaspaDepositSender {
+            wallet,
+            network_id: config.network_id,
+            signer,
+        })
+    }
+
+    /// The active account's receive address, if the wallet has produced one yet.
+    pub async fn receive_address(&self) -> Result<Option<Address>, DepositError> {
+        let account_id = self
+            .wallet
+            .account()
+            .map_err(|e| DepositError::WalletOpen(eyre::eyre!("get account: {}", e)))?
+            .id();
+
+        let accounts = self
+            .wallet
+            .clone()
+            .accounts_enumerate()
+            .await
+            .map_err(|e| DepositError::WalletOpen(eyre::eyre!("enumerate accounts: {}", e)))?;
+
+        Ok(accounts
+            .into_iter()
+            .find(|a| a.account_id == account_id)
+            .and_then(|a| a.receive_address))
+    }
+
+    /// Builds, signs, and broadcasts a deposit transaction paying `amount` sompi to
+    /// `escrow` with `payload` attached, asking the signer to confirm first.
+    ///
+    /// Returns [`DepositError::InvalidAddress`] if `escrow` doesn't parse, and
+    /// [`DepositError::InsufficientFunds`] before touching the signer if the active
+    /// account's mature balance can't cover `amount` plus the estimated fee.
+    ///
+    /// For `Signer::Local` this goes through `Account::send`, which signs with the
+    /// keystore secret directly. A hardware signer's account is watch-only (imported from
+    /// the derived address, holding no private key), so `Account::send` cannot sign for it;
+    /// those transactions are built with a `Generator`, signed input-by-input on the device
+    /// over HID, and submitted directly via [`Self::sign_and_submit_with_device`].
+    pub async fn deposit(
+        &self,
+        escrow: &str,
+        amount: u64,
+        payload: Vec<u8>,
+    ) -> Result<TransactionId, DepositError> {
+        let estimate = self.estimate(escrow, amount, payload.clone()).await?;
+        if !estimate.sufficient_funds() {
+            return Err(DepositError::InsufficientFunds {
+                required: estimate.required,
+                available: estimate.balance.mature,
+            });
+        }
+
+        let escrow = estimate.escrow;
+        self.signer.confirm_on_device(&escrow, amount).await?;
+
+        let account = self
+            .wallet
+            .account()
+            .map_err(|e| DepositError::WalletOpen(eyre::eyre!("get account: {}", e)))?;
+
+        let dst = PaymentDestination::from(PaymentOutput::new(escrow, amount));
+        let fees = Fees::from(0i64);
+        let payload = match payload.len() {
+            0 => None,
+            _ => Some(payload),
+        };
+
+        match &self.signer {
+            Signer::Local(secret) => {
+                let (summary, _) = account
+                    .send(dst, None, fees, payload, secret.clone(), None, &Abortable::new(), None)
+                    .await
+                    .map_err(|e| DepositError::Broadcast(eyre::eyre!("send transaction: {}", e)))?;
+
+                summary.final_transaction_id().ok_or_else(|| {
+                    DepositError::Broadcast(eyre::eyre!("transaction did not produce a transaction ID"))
+                })
+            }
+            Signer::Ledger { .. } | Signer::Trezor { .. } => {
+                self.sign_and_submit_with_device(account, dst, fees, payload).await
+            }
+        }
+    }
+
+    /// Builds the unsigned deposit transaction(s) with a [`Generator`], sends each to the
+    /// hardware device over HID for input-by-input signing, and submits the signed
+    /// transaction directly via RPC. Used in place of `Account::send` for hardware signers,
+    /// whose watch-only account holds no key the wallet could sign with itself.
+    async fn sign_and_submit_with_device(
+        &self,
+        account: Arc<dyn Account>,
+        destination: PaymentDestination,
+        fees: Fees,
+        payload: Option<Vec<u8>>,
+    ) -> Result<TransactionId, DepositError> {
+        let settings = GeneratorSettings::try_new_with_account(account, destination, fees, payload, None)
+            .map_err(|e| DepositError::Broadcast(eyre::eyre!("build transaction generator: {}", e)))?;
+        let mut generator = Generator::try_new(settings, None, None)
+            .map_err(|e| DepositError::Broadcast(eyre::eyre!("create transaction generator: {}", e)))?;
+
+        let mut last_tx_id = None;
+        for pending_tx in generator.by_ref() {
+            let pending_tx = pending_tx
+                .map_err(|e| DepositError::Broadcast(eyre::eyre!("generate transaction: {}", e)))?;
+
+            let signatures = self.signer.sign_inputs(&pending_tx).await?;
+            pending_tx
+                .fill_signatures(signatures)
+                .map_err(|e| DepositError::Broadcast(eyre::eyre!("apply device signatures: {}", e)))?;
+
+            pending_tx
+                .try_submit(self.wallet.rpc_api())
+                .await
+                .map_err(|e| DepositError::Broadcast(eyre::eyre!("submit signed transaction: {}", e)))?;
+
+            last_tx_id = Some(pending_tx.id());
+        }
+
+        last_tx_id.ok_or_else(|| DepositError::Broadcast(eyre::eyre!("generator produced no transactions")))
+    }
+
+    /// Subscribes to the wallet's event stream and blocks until `tx_id` reaches
+    /// `confirmations` confirmations or `timeout` elapses.
+    ///
+    /// `tx_id` matures (becomes spendable) via a single `Events::Maturity` notification once
+    /// it's accepted into a block; after that, each `Events::DaaScoreChange` tick moves the
+    /// chain tip forward, and the confirmation count is the distance between the tip's DAA
+    /// score and the accepting block's DAA score, plus one (maturity itself counts as the
+    /// first confirmation). `confirmations <= 1` is satisfied by maturity alone.
+    pub async fn wait_for_confirmations(
+        &self,
+        tx_id: TransactionId,
+        confirmations: u64,
+        timeout: Duration,
+    ) -> Result<(), DepositError> {
+        let channel = self.wallet.multiplexer().channel();
+        let deadline = Instant::now() + timeout;
+
+        let mut accepting_daa_score: Option<u64> = None;
+        let mut last_confirmations = 0u64;
+
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(DepositError::Timeout {
+                    tx_id,
+                    confirmations,
+                    timeout,
+                    last_confirmations,
+                });
+            }
+
+            let event = match workflow_core::task::timeout(remaining, channel.recv()).await {
+                Ok(Ok(event)) => event,
+                Ok(Err(e)) => {
+                    return Err(DepositError::Connect(eyre::eyre!(
+                        "wallet event stream closed: {}",
+                        e
+                    )))
+                }
+                Err(_) => continue,
+            };
+
+            match event.as_ref() {
+                Events::Pending { record, .. } if record.id() == tx_id => {
+                    eprintln!("deposit {} seen, awaiting maturity...", tx_id);
+                }
+                Events::Maturity { record, .. } if record.id() == tx_id => {
+                    accepting_daa_score = Some(record.block_daa_score());
+                    last_confirmations = 1;
+                    eprintln!(
+                        "deposit {} matured ({}/{} confirmations)",
+                        tx_id, last_confirmations, confirmations
+                    );
+                    if confirmations <= 1 {
+                        return Ok(());
+                    }
+                }
+                Events::DaaScoreChange { current_daa_score } => {
+                    if let Some(accepting_daa_score) = accepting_daa_score {
+                        last_confirmations = current_daa_score.saturating_sub(accepting_daa_score) + 1;
+                        eprintln!(
+                            "deposit {} has {}/{} confirmations",
+                            tx_id, last_confirmations, confirmations
+                        );
+                        if last_confirmations >= confirmations {
+                            return Ok(());
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// The network this sender was connected to.
+    pub fn network_id(&self) -> NetworkId {
+        self.network_id
+    }
+
+    /// Mature and pending sompi balance of the active account.
+    pub async fn balance(&self) -> Result<AccountBalance, DepositError> {
+        let account = self
+            .wallet
+            .account()
+            .map_err(|e| DepositError::WalletOpen(eyre::eyre!("get account: {}", e)))?;
+
+        let balance = account
+            .balance()
+            .ok_or_else(|| DepositError::WalletOpen(eyre::eyre!("account balance not available yet")))?;
+
+        Ok(AccountBalance {
+            mature: balance.mature,
+            pending: balance.pending,
+        })
+    }
+
+    /// Builds (but does not sign or broadcast) the deposit transaction, returning a preview
+    /// of what would be sent: the fee the network would charge and whether the active
+    /// account has enough mature balance to cover `amount` plus that fee.
+    ///
+    /// Returns [`DepositError::InvalidAddress`] if `escrow` doesn't parse as a Kaspa address.
+    pub async fn estimate(
+        &self,
+        escrow: &str,
+        amount: u64,
+        payload: Vec<u8>,
+    ) -> Result<DepositEstimate, DepositError> {
+        let escrow = Address::try_from(escrow)
+            .map_err(|e| DepositError::InvalidAddress(eyre::eyre!("invalid escrow address: {}", e)))?;
+
+        let account = self
+            .wallet
+            .account()
+            .map_err(|e| DepositError::WalletOpen(eyre::eyre!("get account: {}", e)))?;
+        let balance = self.balance().await?;
+
+        let dst = PaymentDestination::from(PaymentOutput::new(escrow.clone(), amount));
+        let fees = Fees::from(0i64);
+        let abortable = Abortable::new();
+
+        let summary = account
+            .estimate(
+                dst,
+                fees,
+                match payload.len() {
+                    0 => None,
+                    _ => Some(payload.clone()),
+                },
+                &abortable,
+            )
+            .await
+            .map_err(|e| DepositError::Broadcast(eyre::eyre!("estimate transaction: {}", e)))?;
+
+        let fee = summary.aggregated_fees();
+        let required = amount
+            .checked_add(fee)
+            .ok_or_else(|| DepositError::InsufficientFunds {
+                required: u64::MAX,
+                available: balance.mature,
+            })?;
+
+        Ok(DepositEstimate {
+            escrow,
+            amount,
+            fee,
+            payload_len: payload.len(),
+            balance,
+            required,
+        })
+    }
+}
+
+/// Mature and pending sompi balance of an account, as reported by [`KaspaDepositSender::balance`].
+#[derive(Copy, Clone, Debug)]
+pub struct AccountBalance {
+    pub mature: u64,
+    pub pending: u64,
+}
+
+/// A preview of a deposit transaction, as built by [`KaspaDepositSender::estimate`].
+#[derive(Clone, Debug)]
+pub struct DepositEstimate {
+    pub escrow: Address,
+    pub amount: u64,
+    pub fee: u64,
+    pub payload_len: usize,
+    pub balance: AccountBalance,
+    /// `amount + fee`, the total that would be debited from the mature balance
+    pub required: u64,
+}
+
+impl DepositEstimate {
+    /// Whether the account's mature balance covers `required`.
+    pub fn sufficient_funds(&self) -> bool {
+        self.balance.mature >= self.required
+    }
+}
+
+impl fmt::Display for DepositEstimate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "escrow:          {}", self.escrow)?;
+        writeln!(f, "amount:          {} sompi", self.amount)?;
+        writeln!(f, "estimated fee:   {} sompi", self.fee)?;
+        writeln!(f, "payload length:  {} bytes", self.payload_len)?;
+        writeln!(f, "total required:  {} sompi", self.required)?;
+        writeln!(f, "mature balance:  {} sompi", self.balance.mature)?;
+        writeln!(f, "pending balance: {} sompi", self.balance.pending)?;
+        write!(
+            f,
+            "sufficient funds: {}",
+            if self.sufficient_funds() { "yes" } else { "no" }
+        )
+    }
+}
@@ -1,201 +1,263 @@
-//! Kaspa deposit sender for Dymension bridge
+//! Kaspa deposit sender CLI for Dymension bridge
 //!
 //! Sends a Kaspa transaction with Hyperlane payload to the escrow address.
 //! Use this after generating the payload with the TypeScript SDK.
 //!
+//! This binary is a thin wrapper over the `kaspa_sender` library crate (see `lib.rs`), which
+//! exposes a `KaspaDepositSender` type with structured `DepositError`s for embedding deposit
+//! sending in another Rust service without shelling out to this CLI.
+//!
 //! Prerequisites:
-//! - A rusty-kaspa wallet file exists at ~/.kaspa/ (or custom --wallet-dir)
-//! - Wallet has sufficient KAS balance
+//! - A rusty-kaspa wallet file exists at ~/.kaspa/ (or custom --wallet-dir),
+//!   unless --signer ledger/trezor is used
+//! - Wallet (or hardware device account) has sufficient KAS balance
+//!
+//! Network, RPC URL, escrow address and wallet directory can be set once via a config file
+//! instead of being passed on every invocation. Run `cargo run -- init` to create one
+//! interactively (default location: ~/.config/dymension-kaspa/config.toml, override with
+//! --config); any value also passed as a flag takes precedence over the file.
 //!
 //! Usage:
+//!   cargo run -- init
+//!   cargo run -- --wallet-secret "your-wallet-password" balance
+//!   cargo run -- --wallet-secret "your-wallet-password" --dry-run --amount "40 KAS" --payload "03000000..."
 //!   cargo run -- \
 //!     --wallet-secret "your-wallet-password" \
-//!     --amount 4000000000 \
+//!     --amount "40 KAS" \
+//!     --payload "03000000..."
+//!
+//!   # sign with a Ledger instead of a local keystore, amount given as raw sompi,
+//!   # with no config file set up
+//!   cargo run -- \
+//!     --signer ledger \
+//!     --hd-path "m/44'/111111'/0'/0/0" \
+//!     --amount 4000000000 --unit sompi \
 //!     --payload "03000000..." \
 //!     --escrow "kaspa:prztt2hd2txge07syjvhaz5j6l9ql6djhc9equela058rjm6vww0uwre5dulh" \
 //!     --network mainnet \
 //!     --rpc "wss://your-kaspa-node:17110"
 
-use clap::Parser;
+mod config;
+
+use clap::{Parser, Subcommand, ValueEnum};
+use config::Config;
 use eyre::Result;
-use kaspa_addresses::Address;
 use kaspa_consensus_core::network::{NetworkId, NetworkType};
-use kaspa_wallet_core::prelude::*;
-use kaspa_wallet_core::storage::local::set_default_storage_folder as unsafe_set_default_storage_folder_kaspa;
-use kaspa_wallet_core::tx::Fees;
-use kaspa_wallet_core::wallet::Wallet;
+use kaspa_sender::amount::{parse_amount, Unit};
+use kaspa_sender::{DepositSenderConfig, KaspaDepositSender, WalletSource, DEFAULT_HD_PATH, LEGACY_LEDGER_HD_PATH};
 use kaspa_wallet_keys::secret::Secret;
-use kaspa_wrpc_client::Resolver;
-use std::sync::Arc;
-use workflow_core::abortable::Abortable;
+use std::path::PathBuf;
+use std::time::Duration;
 
 #[derive(Parser, Debug)]
 #[command(name = "kaspa-sender")]
 #[command(about = "Send Kaspa deposit transaction with Hyperlane payload")]
-struct Args {
-    /// Wallet password (protects the keychain file)
-    #[arg(long)]
-    wallet_secret: String,
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Path to the config file (default: ~/.config/dymension-kaspa/config.toml)
+    #[arg(long, global = true)]
+    config: Option<String>,
+
+    #[command(flatten)]
+    deposit: DepositArgs,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Interactively (re)create the config file and exit, without sending a deposit
+    Init,
+    /// Connect the wallet and report the active account's mature/pending balance
+    Balance,
+}
+
+#[derive(Parser, Debug)]
+struct DepositArgs {
+    /// Where the signing key lives: a local password-protected keystore, or a hardware device
+    #[arg(long, value_enum, default_value_t = SignerKind::Local)]
+    signer: SignerKind,
+
+    /// Wallet password (protects the keychain file). Required when --signer local
+    #[arg(long, required_if_eq("signer", "local"))]
+    wallet_secret: Option<String>,
 
-    /// Custom wallet directory (default: ~/.kaspa/)
+    /// Custom wallet directory (default: ~/.kaspa/, or the config file's wallet_dir)
     #[arg(long)]
     wallet_dir: Option<String>,
 
-    /// Amount in sompi (1 KAS = 100,000,000 sompi)
+    /// BIP32 derivation path for the signing key. Ignored for --signer local
+    #[arg(long, default_value = DEFAULT_HD_PATH)]
+    hd_path: String,
+
+    /// Use the legacy Ledger Kaspa app derivation path instead of --hd-path
     #[arg(long)]
-    amount: u64,
+    ledger_legacy: bool,
+
+    /// Amount to send, in the unit selected by --unit (e.g. "40", "40.0 KAS", or raw sompi)
+    #[arg(long)]
+    amount: Option<String>,
+
+    /// Unit that --amount is expressed in
+    #[arg(long, value_enum, default_value_t = Unit::Kas)]
+    unit: Unit,
 
     /// Hyperlane message payload (hex encoded, from TypeScript SDK)
     #[arg(long)]
-    payload: String,
+    payload: Option<String>,
+
+    /// Escrow address to send to (overrides the config file)
+    #[arg(long)]
+    escrow: Option<String>,
+
+    /// Network: mainnet or testnet (overrides the config file)
+    #[arg(long)]
+    network: Option<String>,
+
+    /// Kaspa WRPC URL, e.g. wss://your-node:17110 (overrides the config file)
+    #[arg(long)]
+    rpc: Option<String>,
 
-    /// Escrow address to send to
+    /// Block until the deposit reaches this many confirmations before exiting
     #[arg(long)]
-    escrow: String,
+    wait_confirmations: Option<u64>,
 
-    /// Network (mainnet or testnet)
-    #[arg(long, default_value = "mainnet")]
-    network: String,
+    /// Give up waiting for confirmations after this many seconds
+    #[arg(long, default_value_t = 120, requires = "wait_confirmations")]
+    timeout: u64,
 
-    /// Kaspa WRPC URL (e.g. wss://your-node:17110)
+    /// Build and preview the deposit transaction (balance, amount, fee) without broadcasting
     #[arg(long)]
-    rpc: String,
+    dry_run: bool,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+enum SignerKind {
+    /// Local password-protected rusty-kaspa keystore (the historical default)
+    Local,
+    /// Ledger hardware wallet, over HID, with on-device confirmation
+    Ledger,
+    /// Trezor hardware wallet, over HID, with on-device confirmation
+    Trezor,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let args = Args::parse();
+    let cli = Cli::parse();
+
+    let config_path = match &cli.config {
+        Some(path) => PathBuf::from(path),
+        None => Config::default_path()?,
+    };
+
+    if matches!(cli.command, Some(Command::Init)) {
+        config::query_user_for_initial_config(&config_path)?;
+        return Ok(());
+    }
+
+    let config = config::initial_setup(&config_path)?;
+    let args = cli.deposit;
 
-    let network_id = match args.network.as_str() {
+    let network = args
+        .network
+        .or(config.network)
+        .ok_or_else(|| eyre::eyre!("--network is required (set it via flag or config file)"))?;
+    let rpc = args
+        .rpc
+        .or(config.rpc)
+        .ok_or_else(|| eyre::eyre!("--rpc is required (set it via flag or config file)"))?;
+    let wallet_dir = args.wallet_dir.or(config.wallet_dir);
+
+    let network_id = match network.as_str() {
         "mainnet" => NetworkId::new(NetworkType::Mainnet),
         "testnet" => NetworkId::with_suffix(NetworkType::Testnet, 10),
         other => return Err(eyre::eyre!("unknown network: {}", other)),
     };
 
-    let secret = Secret::from(args.wallet_secret);
+    let hd_path = if args.ledger_legacy {
+        LEGACY_LEDGER_HD_PATH.to_string()
+    } else {
+        args.hd_path.clone()
+    };
+
+    let wallet_source = match args.signer {
+        SignerKind::Local => {
+            let wallet_secret = args
+                .wallet_secret
+                .ok_or_else(|| eyre::eyre!("--wallet-secret is required for --signer local"))?;
+            WalletSource::Local {
+                secret: Secret::from(wallet_secret),
+                wallet_dir,
+            }
+        }
+        SignerKind::Ledger => WalletSource::Ledger { hd_path },
+        SignerKind::Trezor => WalletSource::Trezor { hd_path },
+    };
 
     eprintln!("initializing kaspa wallet...");
-    let wallet = get_wallet(&secret, network_id, args.rpc, args.wallet_dir).await?;
+    let sender = KaspaDepositSender::connect(DepositSenderConfig {
+        network_id,
+        rpc_url: rpc,
+        wallet_source,
+    })
+    .await?;
+
+    if let Some(receive_address) = sender.receive_address().await? {
+        eprintln!("wallet ready: receive_address={}", receive_address);
+    }
+
+    if matches!(cli.command, Some(Command::Balance)) {
+        let balance = sender.balance().await?;
+        println!("mature:  {} sompi", balance.mature);
+        println!("pending: {} sompi", balance.pending);
+        return Ok(());
+    }
 
-    let escrow_address = Address::try_from(args.escrow)?;
-    let payload = hex::decode(&args.payload)?;
+    let escrow = args
+        .escrow
+        .or(config.escrow)
+        .ok_or_else(|| eyre::eyre!("--escrow is required (set it via flag or config file)"))?;
+    let amount = args
+        .amount
+        .ok_or_else(|| eyre::eyre!("--amount is required"))?;
+    let payload = args
+        .payload
+        .ok_or_else(|| eyre::eyre!("--payload is required"))?;
+
+    let payload = hex::decode(&payload)?;
+    let amount_sompi =
+        parse_amount(&amount, args.unit).map_err(|e| eyre::eyre!("invalid --amount: {}", e))?;
+
+    if args.dry_run {
+        let estimate = sender.estimate(&escrow, amount_sompi, payload).await?;
+        println!("{}", estimate);
+        if !estimate.sufficient_funds() {
+            eprintln!("dry run: insufficient funds, aborting without broadcasting");
+            std::process::exit(1);
+        }
+        eprintln!("dry run: transaction not broadcast");
+        return Ok(());
+    }
 
     eprintln!(
         "sending deposit: amount={} sompi, escrow={}, payload_len={}",
-        args.amount,
-        escrow_address,
+        amount_sompi,
+        escrow,
         payload.len()
     );
 
-    let tx_id = deposit_with_payload(&wallet, &secret, escrow_address, args.amount, payload).await?;
+    let tx_id = sender.deposit(&escrow, amount_sompi, payload).await?;
 
     println!("{}", tx_id);
     eprintln!("transaction submitted successfully");
 
-    Ok(())
-}
-
-async fn get_wallet(
-    s: &Secret,
-    network_id: NetworkId,
-    url: String,
-    storage_folder: Option<String>,
-) -> Result<Arc<Wallet>> {
-    if let Some(storage_folder) = storage_folder {
-        unsafe { unsafe_set_default_storage_folder_kaspa(storage_folder) }
-            .map_err(|e| eyre::eyre!("failed to set storage folder: {}", e))?;
-    }
-
-    let local_store = Wallet::local_store()
-        .map_err(|e| eyre::eyre!("failed to open wallet local store: {}", e))?;
-
-    let w = Arc::new(
-        Wallet::try_new(local_store, Some(Resolver::default()), Some(network_id))
-            .map_err(|e| eyre::eyre!("failed to create wallet: {}", e))?,
-    );
-
-    w.start()
-        .await
-        .map_err(|e| eyre::eyre!("failed to start wallet: {}", e))?;
-
-    w.clone()
-        .connect(Some(url), &network_id)
-        .await
-        .map_err(|e| eyre::eyre!("failed to connect wallet: {}", e))?;
-
-    if !w.is_connected() {
-        return Err(eyre::eyre!("wallet not connected"));
+    if let Some(confirmations) = args.wait_confirmations {
+        eprintln!("waiting for {} confirmations on {}...", confirmations, tx_id);
+        sender
+            .wait_for_confirmations(tx_id, confirmations, Duration::from_secs(args.timeout))
+            .await?;
+        eprintln!("deposit {} confirmed", tx_id);
     }
 
-    w.clone()
-        .wallet_open(s.clone(), None, true, false)
-        .await
-        .map_err(|e| eyre::eyre!("failed to open wallet: {}", e))?;
-
-    let accounts = w
-        .clone()
-        .accounts_enumerate()
-        .await
-        .map_err(|e| eyre::eyre!("failed to enumerate accounts: {}", e))?;
-
-    let account_descriptor = accounts
-        .first()
-        .ok_or_else(|| eyre::eyre!("wallet has no accounts"))?;
-
-    let account_id = account_descriptor.account_id;
-
-    w.clone()
-        .accounts_select(Some(account_id))
-        .await
-        .map_err(|e| eyre::eyre!("failed to select wallet account: {}", e))?;
-
-    w.clone()
-        .accounts_activate(Some(vec![account_id]))
-        .await
-        .map_err(|e| eyre::eyre!("failed to activate wallet account: {}", e))?;
-
-    eprintln!(
-        "wallet ready: receive_address={}",
-        account_descriptor.receive_address.as_ref().unwrap()
-    );
-
-    Ok(w)
-}
-
-async fn deposit_with_payload(
-    w: &Arc<Wallet>,
-    secret: &Secret,
-    address: Address,
-    amt: u64,
-    payload: Vec<u8>,
-) -> Result<TransactionId> {
-    let a = w
-        .account()
-        .map_err(|e| eyre::eyre!("failed to get account: {}", e))?;
-
-    let dst = PaymentDestination::from(PaymentOutput::new(address, amt));
-    let fees = Fees::from(0i64);
-    let payment_secret = None;
-    let abortable = Abortable::new();
-
-    let (summary, _) = a
-        .send(
-            dst,
-            None,
-            fees,
-            match payload.len() {
-                0 => None,
-                _ => Some(payload),
-            },
-            secret.clone(),
-            payment_secret,
-            &abortable,
-            None,
-        )
-        .await
-        .map_err(|e| eyre::eyre!("failed to send transaction: {}", e))?;
-
-    summary
-        .final_transaction_id()
-        .ok_or_else(|| eyre::eyre!("transaction did not produce a transaction ID"))
+    Ok(())
 }